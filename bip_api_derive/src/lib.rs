@@ -0,0 +1,207 @@
+//! Derive macro for `bip_api::scrapers::from_selector::FromSelector`.
+//!
+//! `#[derive(FromSelector)]` turns a struct into a declarative scraper: each
+//! field's `#[xpath(...)]` attribute becomes one `findnodes` call against
+//! the parsed document, dispatched on the field's type (`T`, `Option<T>`,
+//! `Vec<T>`).
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, GenericArgument, Lit, Meta, NestedMeta, PathArguments, Type};
+
+#[proc_macro_derive(FromSelector, attributes(xpath))]
+pub fn derive_from_selector(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => panic!("FromSelector only supports structs with named fields"),
+        },
+        _ => panic!("FromSelector can only be derived for structs"),
+    };
+
+    let field_builders = fields.iter().map(|field| {
+        let ident = field.ident.as_ref().expect("named field");
+        let field_name = ident.to_string();
+        let attr = FieldAttr::parse(field);
+        let xpath = &attr.xpath;
+
+        // `select_attr` yields `Vec<Url>`, `select_text` yields `Vec<String>`,
+        // optionally normalised through `strip_whitespaces_and_linebreaks`.
+        let lookup = match &attr.attr {
+            Some(name) => quote! { selector.select_attr(#xpath, #name)? },
+            None if attr.trim => quote! {
+                selector
+                    .select_text(#xpath)?
+                    .into_iter()
+                    .map(|s| bip_api::parsing::strip_whitespaces_and_linebreaks(&s))
+                    .collect::<Vec<_>>()
+            },
+            None => quote! { selector.select_text(#xpath)? },
+        };
+
+        match FieldShape::of(&field.ty) {
+            FieldShape::Option(_) => quote! {
+                #ident: #lookup.into_iter().next()
+            },
+            FieldShape::Vec(_) => quote! {
+                #ident: #lookup
+            },
+            FieldShape::Plain => quote! {
+                #ident: #lookup
+                    .into_iter()
+                    .next()
+                    .ok_or(bip_api::scrapers::ScrapeError::MissingField(#field_name))?
+            },
+        }
+    });
+
+    let expanded = quote! {
+        impl bip_api::scrapers::from_selector::FromSelector for #name {
+            fn from_selector(
+                selector: &bip_api::scrapers::selector::Selector,
+            ) -> Result<Self, bip_api::scrapers::ScrapeError> {
+                Ok(Self {
+                    #(#field_builders),*
+                })
+            }
+        }
+    };
+
+    expanded.into()
+}
+
+enum FieldShape<'a> {
+    Plain,
+    Option(&'a Type),
+    Vec(&'a Type),
+}
+
+impl<'a> FieldShape<'a> {
+    fn of(ty: &'a Type) -> Self {
+        if let Some(inner) = inner_type_of("Option", ty) {
+            return FieldShape::Option(inner);
+        }
+        if let Some(inner) = inner_type_of("Vec", ty) {
+            return FieldShape::Vec(inner);
+        }
+        FieldShape::Plain
+    }
+}
+
+fn inner_type_of<'a>(wrapper: &str, ty: &'a Type) -> Option<&'a Type> {
+    let Type::Path(path) = ty else { return None };
+    let segment = path.path.segments.last()?;
+    if segment.ident != wrapper {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else { return None };
+    match args.args.first()? {
+        GenericArgument::Type(inner) => Some(inner),
+        _ => None,
+    }
+}
+
+struct FieldAttr {
+    xpath: String,
+    attr: Option<String>,
+    trim: bool,
+}
+
+impl FieldAttr {
+    fn parse(field: &syn::Field) -> Self {
+        let meta = field
+            .attrs
+            .iter()
+            .find(|a| a.path.is_ident("xpath"))
+            .expect("field is missing #[xpath(...)]")
+            .parse_meta()
+            .expect("malformed #[xpath(...)] attribute");
+
+        let Meta::List(list) = meta else { panic!("expected #[xpath(\"...\", ...)]") };
+        let mut items = list.nested.iter();
+
+        let xpath = match items.next() {
+            Some(NestedMeta::Lit(Lit::Str(s))) => s.value(),
+            _ => panic!("#[xpath(...)] must start with a string literal"),
+        };
+
+        let mut attr = None;
+        let mut trim = false;
+        for item in items {
+            match item {
+                NestedMeta::Meta(Meta::Path(p)) if p.is_ident("optional") => {}
+                NestedMeta::Meta(Meta::Path(p)) if p.is_ident("trim") => trim = true,
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("attr") => {
+                    if let Lit::Str(s) = &nv.lit {
+                        attr = Some(s.value());
+                    }
+                }
+                _ => {}
+            }
+        }
+        Self { xpath, attr, trim }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse::Parser;
+
+    use super::*;
+
+    #[test]
+    fn field_shape_detects_option_and_vec_wrappers() {
+        let plain: Type = syn::parse_str("String").unwrap();
+        let option: Type = syn::parse_str("Option<String>").unwrap();
+        let vec: Type = syn::parse_str("Vec<Url>").unwrap();
+
+        assert!(matches!(FieldShape::of(&plain), FieldShape::Plain));
+        assert!(matches!(FieldShape::of(&option), FieldShape::Option(_)));
+        assert!(matches!(FieldShape::of(&vec), FieldShape::Vec(_)));
+    }
+
+    #[test]
+    fn inner_type_of_unwraps_the_generic_argument() {
+        let option: Type = syn::parse_str("Option<String>").unwrap();
+        let inner = inner_type_of("Option", &option).unwrap();
+        assert_eq!(quote::quote!(#inner).to_string(), "String");
+
+        let plain: Type = syn::parse_str("String").unwrap();
+        assert!(inner_type_of("Option", &plain).is_none());
+    }
+
+    #[test]
+    fn field_attr_parses_xpath_attr_and_trim() {
+        let field: syn::Field = syn::Field::parse_named
+            .parse2(quote::quote! {
+                #[xpath("//a/@href", attr = "href", trim)]
+                link: String
+            })
+            .unwrap();
+
+        let attr = FieldAttr::parse(&field);
+        assert_eq!(attr.xpath, "//a/@href");
+        assert_eq!(attr.attr.as_deref(), Some("href"));
+        assert!(attr.trim);
+    }
+
+    #[test]
+    fn field_attr_defaults_attr_and_trim_when_absent() {
+        let field: syn::Field = syn::Field::parse_named
+            .parse2(quote::quote! {
+                #[xpath("//fieldset", optional)]
+                summary: Option<String>
+            })
+            .unwrap();
+
+        let attr = FieldAttr::parse(&field);
+        assert_eq!(attr.xpath, "//fieldset");
+        assert_eq!(attr.attr, None);
+        assert!(!attr.trim);
+    }
+}
@@ -4,70 +4,91 @@ use reqwest::RequestBuilder;
 use tokio;
 use tower::{self, Service, ServiceBuilder};
 
+use std::collections::HashMap;
+
 use bip_api::parsing::BipData;
 use bip_api::scrapers::{
-    default_client, pipelines::StdOutPipeline, retry::RetryLimit, selector::Selector, utils::open_in_browser,
+    concurrency::{FetchOutcome, FetchQueue},
+    default_client,
+    from_selector::FromSelector,
+    integrity::{self, SubresourceIntegrity},
+    pipelines::StdOutPipeline,
+    retry::RetryLimit,
+    selector::Selector,
+    ScrapeError,
 };
 
+/// How many detail pages may be in flight at once.
+const FETCH_CONCURRENCY: usize = 10;
+
 #[tokio::main]
-async fn main() {
+async fn main() -> Result<(), ScrapeError> {
     /// The Url which has to be hit to set the cookies for subsequent search queries
     const COOKIE_LANDING: &str = "http://dipbt.bundestag.de/dip21.web/bt";
 
     let search_url = "http://dipbt.bundestag.de/dip21.web/searchProcedures/advanced_search_list.do";
 
-    let client = default_client().unwrap();
+    let client = default_client()?;
     let svc = tower::service_fn(|req_builder: RequestBuilder| req_builder.send());
 
     let mut svc = ServiceBuilder::new()
         .rate_limit(10, std::time::Duration::from_secs(1))
 	.retry(RetryLimit::new(3))
+        .buffer(FETCH_CONCURRENCY * 4)
         .service(svc);
-    poll_fn(|mut cx| svc.poll_ready(&mut cx)).await.unwrap();
+    poll_fn(|mut cx| svc.poll_ready(&mut cx)).await?;
+
+    let fetch_queue = FetchQueue::builder()
+        .concurrency(FETCH_CONCURRENCY)
+        .queue_size(FETCH_CONCURRENCY * 4)
+        .build();
 
     let item_pipeline = StdOutPipeline;
 
+    // No digests are pinned yet (the Bundestag publishes none), but every
+    // fetched detail page already runs through `integrity::verify_body`
+    // below, so wiring a manifest in later is a one-line change here.
+    let expected_digests: HashMap<url::Url, SubresourceIntegrity> = HashMap::new();
+
     // Get cookies
-    svc.call(client.get(COOKIE_LANDING)).await.unwrap();
-    let resp = svc.call(client.get(search_url)).await.unwrap();
-    let sel = Selector::from_response(resp).await.unwrap();
+    svc.call(client.get(COOKIE_LANDING)).await?;
+    let resp = svc.call(client.get(search_url)).await?;
+    let sel = Selector::from_response(resp).await?;
 
-    let mut form = sel.form_data("//form").unwrap();
+    let mut form = sel.form_data("//form")?;
     // Add the parameters needed to kick off the search
     form.push(("method".to_string(), "Suchen".to_string()));
 
     loop {
         let req = client.post(search_url).form(&form);
 
-        let resp = svc.call(req).await.unwrap();
-        let sel = Selector::from_response(resp).await.unwrap();
-        let links = sel
-            .select_links("//div[@class='tabelleGross']//a[@class='linkIntern']/@href")
-            .unwrap();
+        let resp = svc.call(req).await?;
+        let sel = Selector::from_response(resp).await?;
+        let links = sel.select_links("//div[@class='tabelleGross']//a[@class='linkIntern']/@href")?;
         let links = stream::iter(links)
-            .then(|l| svc.call(client.get(dbg!(l))))
-            .map_err(|e| Into::into(e))
+            .map(|l| {
+                let mut svc = svc.clone();
+                let client = client.clone();
+                let fetch_queue = &fetch_queue;
+                async move {
+                    match fetch_queue.run(|| svc.call(client.get(dbg!(l)))).await {
+                        FetchOutcome::Completed(resp) => resp.map_err(ScrapeError::from),
+                        FetchOutcome::Overloaded => Err(ScrapeError::Overloaded),
+                    }
+                }
+            })
+            .buffer_unordered(FETCH_CONCURRENCY)
             .and_then(|resp| async {
                 let sel = Selector::from_response(resp).await?;
-		// open_in_browser(&sel.body()).unwrap();
-                BipData::builder()
-                    .content(
-                        sel.select_text("//fieldset[h1[contains(text(), 'Inhalt')]]")?.pop().clone(),
-                    )
-                    .summary(
-                        sel.select_text("//fieldset[h1[contains(text(), 'Basisinformationen')]]")?
-                            [0]
-                        .clone(),
-                    )
-                    .tag_words(
-                        sel.select_text("//fieldset[h1[contains(text(), 'Schlagwörter')]]")?.pop()
-                            .clone(),
-                    )
-                    .build()
+                Ok((sel.url().clone(), sel.body().to_vec(), sel))
             });
+        let links = links.and_then(|(url, body, sel)| async move {
+            integrity::verify_body(&url, body, &expected_digests)?;
+            Ok(BipData::from_selector(&sel)?)
+        });
         item_pipeline.pipe_out(links).await;
         // Prepare the form for the next iteration
-        form = sel.form_data("//form").unwrap();
+        form = sel.form_data("//form")?;
         // Add the parameters needed to kick of the search
         form.push(("method".to_string(), ">".to_string()));
     }
@@ -0,0 +1,237 @@
+//! A `reqwest::cookie::CookieStore` that can be serialized to disk, so a
+//! `BipClient` session survives restarts instead of re-establishing the
+//! landing-page session on every run.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use cookie::Cookie as RawCookie;
+use reqwest::cookie::CookieStore;
+use reqwest::header::HeaderValue;
+use url::Url;
+
+#[derive(Debug, Clone)]
+struct Record {
+    domain: String,
+    include_subdomains: bool,
+    path: String,
+    secure: bool,
+    expires: Option<u64>,
+    name: String,
+    value: String,
+}
+
+impl Record {
+    fn is_expired(&self, now: u64) -> bool {
+        self.expires.map(|exp| exp <= now).unwrap_or(false)
+    }
+
+    fn matches(&self, url: &Url) -> bool {
+        let host = url.host_str().unwrap_or("");
+        let host_matches = if self.include_subdomains {
+            host == self.domain || host.ends_with(&format!(".{}", self.domain))
+        } else {
+            host == self.domain
+        };
+        host_matches && url.path().starts_with(&self.path) && (!self.secure || url.scheme() == "https")
+    }
+}
+
+/// An on-disk, shareable cookie jar.
+///
+/// Cookies are persisted one-per-line as
+/// `domain\tinclude_subdomains\tpath\tsecure\texpires\tname\tvalue`, which
+/// keeps the format as easy to eyeball as the rest of this crate's
+/// hand-rolled form/query encoding.
+#[derive(Debug, Default)]
+pub struct CookieJar {
+    records: Mutex<Vec<Record>>,
+}
+
+impl CookieJar {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a jar from `path`, dropping any cookie that has already
+    /// expired. Missing files yield an empty jar.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::new()),
+            Err(e) => return Err(e),
+        };
+        let now = now();
+        let mut records = vec![];
+        for line in contents.lines() {
+            let fields: Vec<&str> = line.splitn(7, '\t').collect();
+            let [domain, include_subdomains, path, secure, expires, name, value] = fields[..] else {
+                continue;
+            };
+            let record = Record {
+                domain: domain.to_string(),
+                include_subdomains: include_subdomains == "1",
+                path: path.to_string(),
+                secure: secure == "1",
+                expires: expires.parse().ok().filter(|&e| e != 0),
+                name: name.to_string(),
+                value: value.to_string(),
+            };
+            if !record.is_expired(now) {
+                records.push(record);
+            }
+        }
+        Ok(Self { records: Mutex::new(records) })
+    }
+
+    /// Write the current jar out to `path`, dropping anything expired by now.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let now = now();
+        let records = self.records.lock().unwrap();
+        let mut out = String::new();
+        for record in records.iter().filter(|r| !r.is_expired(now)) {
+            out.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                record.domain,
+                record.include_subdomains as u8,
+                record.path,
+                record.secure as u8,
+                record.expires.unwrap_or(0),
+                record.name,
+                record.value,
+            ));
+        }
+        fs::write(path, out)
+    }
+
+    /// Whether the jar holds any (non-expired) cookie usable for `url`.
+    pub fn has_cookie_for(&self, url: &Url) -> bool {
+        let now = now();
+        self.records.lock().unwrap().iter().any(|r| !r.is_expired(now) && r.matches(url))
+    }
+}
+
+impl CookieStore for CookieJar {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+        let mut records = self.records.lock().unwrap();
+        let host = url.host_str().unwrap_or("").to_string();
+        for header in cookie_headers {
+            let Ok(raw) = header.to_str() else { continue };
+            let Ok(parsed) = RawCookie::parse(raw.to_string()) else { continue };
+
+            let domain = parsed.domain().map(str::to_string).unwrap_or_else(|| host.clone());
+            let include_subdomains = parsed.domain().is_some();
+            let path = parsed.path().unwrap_or("/").to_string();
+            let expires = parsed.max_age().map(|age| now() + age.whole_seconds().max(0) as u64).or_else(
+                || {
+                    parsed
+                        .expires_datetime()
+                        .map(|dt| dt.unix_timestamp().max(0) as u64)
+                },
+            );
+
+            records.retain(|r| !(r.domain == domain && r.path == path && r.name == parsed.name()));
+            records.push(Record {
+                domain,
+                include_subdomains,
+                path,
+                secure: parsed.secure().unwrap_or(false),
+                expires,
+                name: parsed.name().to_string(),
+                value: parsed.value().to_string(),
+            });
+        }
+    }
+
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        let now = now();
+        let records = self.records.lock().unwrap();
+        let cookie_str = records
+            .iter()
+            .filter(|r| !r.is_expired(now) && r.matches(url))
+            .map(|r| format!("{}={}", r.name, r.value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        if cookie_str.is_empty() {
+            None
+        } else {
+            HeaderValue::from_str(&cookie_str).ok()
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(domain: &str, include_subdomains: bool, path: &str, secure: bool) -> Record {
+        Record {
+            domain: domain.to_string(),
+            include_subdomains,
+            path: path.to_string(),
+            secure,
+            expires: None,
+            name: "session".to_string(),
+            value: "abc".to_string(),
+        }
+    }
+
+    #[test]
+    fn matches_exact_domain_only_without_subdomains() {
+        let r = record("example.com", false, "/", false);
+        assert!(r.matches(&Url::parse("http://example.com/").unwrap()));
+        assert!(!r.matches(&Url::parse("http://sub.example.com/").unwrap()));
+    }
+
+    #[test]
+    fn matches_subdomains_when_domain_attribute_was_set() {
+        let r = record("example.com", true, "/", false);
+        assert!(r.matches(&Url::parse("http://example.com/").unwrap()));
+        assert!(r.matches(&Url::parse("http://sub.example.com/").unwrap()));
+        assert!(!r.matches(&Url::parse("http://notexample.com/").unwrap()));
+    }
+
+    #[test]
+    fn matches_path_prefix_only() {
+        let r = record("example.com", false, "/app", false);
+        assert!(r.matches(&Url::parse("http://example.com/app/page").unwrap()));
+        assert!(!r.matches(&Url::parse("http://example.com/other").unwrap()));
+    }
+
+    #[test]
+    fn secure_cookies_require_https() {
+        let r = record("example.com", false, "/", true);
+        assert!(r.matches(&Url::parse("https://example.com/").unwrap()));
+        assert!(!r.matches(&Url::parse("http://example.com/").unwrap()));
+    }
+
+    #[test]
+    fn is_expired_compares_against_now() {
+        let mut r = record("example.com", false, "/", false);
+        r.expires = Some(100);
+        assert!(r.is_expired(100));
+        assert!(r.is_expired(200));
+        assert!(!r.is_expired(50));
+    }
+
+    #[test]
+    fn save_and_load_round_trips_non_expired_cookies() {
+        let jar = CookieJar::new();
+        jar.records.lock().unwrap().push(record("example.com", true, "/", true));
+
+        let path = std::env::temp_dir().join(format!("scraping-dbg-cookie-jar-test-{}", std::process::id()));
+        jar.save(&path).unwrap();
+
+        let loaded = CookieJar::load(&path).unwrap();
+        assert!(loaded.has_cookie_for(&Url::parse("https://sub.example.com/").unwrap()));
+
+        let _ = fs::remove_file(&path);
+    }
+}
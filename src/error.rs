@@ -0,0 +1,44 @@
+use thiserror::Error;
+
+use crate::scrapers::mime::Mime;
+
+/// Crate-wide error for the `scrapers`/`parsing` pipeline.
+///
+/// Threaded through `Selector::from_response`, `form_data`, `select_links`,
+/// `select_text` and the `BipData` extraction so a single bad detail page
+/// can be logged and skipped instead of taking down the whole scrape with
+/// an `unwrap()` panic.
+#[derive(Debug, Error)]
+pub enum ScrapeError {
+    #[error("Network error")]
+    Http(#[from] reqwest::Error),
+    #[error("No node matched xpath: {xpath}")]
+    SelectorNotFound { xpath: String },
+    #[error("No <form> found on the page")]
+    FormMissing,
+    #[error("Document has no root element")]
+    NoRootElement,
+    #[error("Response body exceeds the {limit}-byte limit")]
+    BodyTooLarge { limit: usize },
+    /// Reserved for callers that track their own retry budget (e.g. a
+    /// manual retry loop) and want to report it as exhausted rather than
+    /// surfacing the last underlying error.
+    #[error("Retries exhausted")]
+    RetriesExhausted,
+    #[error("Field not set: {0}")]
+    MissingField(&'static str),
+    #[error("Don't know how to parse a {0:?} response")]
+    UnsupportedMedia(Mime),
+    #[error("Integrity check failed for {0}: body does not match the expected digest")]
+    IntegrityMismatch(String),
+    #[error("Too many in-flight requests; this fetch was shed")]
+    Overloaded,
+    #[error("Requests whose body is a stream cannot be cloned")]
+    RequestCloneError(String),
+    #[error("Parse error: {0}")]
+    Parse(String),
+    /// Catch-all for the boxed errors that come out of a `tower` stack once
+    /// a `.buffer()` layer has erased the underlying service's `Error` type.
+    #[error(transparent)]
+    Service(#[from] tower::BoxError),
+}
@@ -1,4 +1,6 @@
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
 use std::time::Duration;
 
 use futures::stream::{iter, once, Stream, StreamExt};
@@ -8,8 +10,20 @@ use reqwest::{self, Client, ClientBuilder};
 use thiserror::Error;
 use url::Url;
 
+// `#[derive(FromSelector)]` expands to absolute `bip_api::...` paths (see
+// `bip_api_derive`), which only resolve for a crate's own types once it is
+// also reachable under its own name.
+extern crate self as bip_api;
+
+pub mod cookie_jar;
+pub mod error;
 pub mod parsing;
 pub mod engine;
+pub mod scrapers;
+
+pub use bip_api_derive::FromSelector;
+pub use cookie_jar::CookieJar;
+pub use error::ScrapeError;
 
 #[derive(Debug, PartialEq)]
 pub enum Citation {
@@ -33,6 +47,9 @@ const PARALLEL_DOWNLOADS: usize = 1;
 pub struct BipClient {
     /// Client with necessary cookies set
     cookied_client: Client,
+    /// The jar backing `cookied_client`, kept around so its cookies can be
+    /// persisted with `save_cookies`.
+    cookie_jar: Arc<CookieJar>,
 }
 
 #[derive(Error, Debug)]
@@ -67,10 +84,10 @@ pub enum ElectionPeriod {
 }
 
 /// Construct a default client with the correct USER_AGENT and
-/// timeouts set.
+/// timeouts set, backed by `jar`.
 /// This client is sufficient for accessing the details page, but not
 /// for performing queries. For the latter, construct a `BipClient`.
-fn default_client() -> Result<Client, ApiError> {
+fn default_client(jar: Arc<CookieJar>) -> Result<Client, ApiError> {
     let mut headers = header::HeaderMap::new();
     headers.insert(
         header::USER_AGENT,
@@ -79,7 +96,7 @@ fn default_client() -> Result<Client, ApiError> {
 
     Ok(ClientBuilder::new()
         .default_headers(headers)
-        .cookie_store(true)
+        .cookie_provider(jar)
         .timeout(Duration::from_secs(20))
         .build()?)
 }
@@ -87,17 +104,37 @@ fn default_client() -> Result<Client, ApiError> {
 impl BipClient {
     /// Create a client which has the necessary cookies set for subsequent queries
     pub async fn new() -> Result<Self, ApiError> {
-        let cookied_client = default_client()?;
-        let resp = cookied_client
-            .get(COOKIE_LANDING)
-            .send()
-            .await?;
+        Self::with_jar(Arc::new(CookieJar::new()), true).await
+    }
+
+    /// Restore a session previously persisted with `save_cookies`.
+    ///
+    /// If none of the restored cookies are still valid (e.g. the session
+    /// expired while the process was down), this transparently re-hits
+    /// `COOKIE_LANDING` instead of handing back a client doomed to get
+    /// `ApiError::LoggedOut` on its first search.
+    pub async fn from_saved(path: impl AsRef<Path>) -> Result<Self, ApiError> {
+        let jar = Arc::new(CookieJar::load(path).map_err(|e| ApiError::ParsingError(e.to_string()))?);
+        let needs_landing = !jar.has_cookie_for(&Url::parse(COOKIE_LANDING).unwrap());
+        Self::with_jar(jar, needs_landing).await
+    }
 
-        // Make sure that we actually got a cookie!
-        if resp.cookies().count() == 0 {
-            return Err(ApiError::NoCookie);
+    async fn with_jar(jar: Arc<CookieJar>, hit_landing: bool) -> Result<Self, ApiError> {
+        let cookied_client = default_client(jar.clone())?;
+        if hit_landing {
+            let resp = cookied_client.get(COOKIE_LANDING).send().await?;
+            // Make sure that we actually got a cookie!
+            if resp.cookies().count() == 0 {
+                return Err(ApiError::NoCookie);
+            }
         }
-        Ok(Self { cookied_client })
+        Ok(Self { cookied_client, cookie_jar: jar })
+    }
+
+    /// Persist the session's cookies to `path` so a later `from_saved` can
+    /// resume without re-establishing the landing-page session.
+    pub fn save_cookies(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        self.cookie_jar.save(path)
     }
 
     /// Stream over detail view pages for the given year and period
@@ -0,0 +1,568 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::future::Future;
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use reqwest::{header, RequestBuilder, Response};
+use tower::{Layer, Service};
+
+use crate::scrapers::ScrapeError;
+
+/// Default freshness lifetime applied when a response carries neither
+/// `Cache-Control: max-age` nor `Expires`.
+const DEFAULT_FRESHNESS: Duration = Duration::from_secs(0);
+
+/// A `tower` [`Layer`] wrapping a service in an on-disk HTTP cache.
+///
+/// Entries are addressed by method + URL, plus (for POST requests such as
+/// the Bundestag search form) a hash of the form body. This lets a scrape
+/// be interrupted and resumed without re-downloading every detail page.
+#[derive(Clone, Debug)]
+pub struct HttpCacheLayer {
+    dir: PathBuf,
+    cache_non_get: bool,
+}
+
+impl HttpCacheLayer {
+    /// Cache entries under `dir`, creating it if necessary.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            cache_non_get: false,
+        }
+    }
+
+    /// Also cache non-GET requests (e.g. the POST search pages). Off by
+    /// default, since caching a POST is only correct when the caller knows
+    /// the body fully determines the response.
+    pub fn cache_non_get(mut self, yes: bool) -> Self {
+        self.cache_non_get = yes;
+        self
+    }
+}
+
+impl<S> Layer<S> for HttpCacheLayer {
+    type Service = HttpCache<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        HttpCache {
+            inner,
+            store: Store::new(&self.dir),
+            cache_non_get: self.cache_non_get,
+        }
+    }
+}
+
+/// The caching `tower::Service` itself. Wraps `inner`, which actually sends
+/// requests, and consults/updates `store` around each call.
+#[derive(Clone, Debug)]
+pub struct HttpCache<S> {
+    inner: S,
+    store: Store,
+    cache_non_get: bool,
+}
+
+impl<S> Service<RequestBuilder> for HttpCache<S>
+where
+    S: Service<RequestBuilder, Response = Response, Error = reqwest::Error> + Clone + Send + 'static,
+    S::Future: Send,
+{
+    type Response = Response;
+    type Error = ScrapeError;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, ScrapeError>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(ScrapeError::Http)
+    }
+
+    fn call(&mut self, req: RequestBuilder) -> Self::Future {
+        let mut inner = self.inner.clone();
+        let store = self.store.clone();
+        let cache_non_get = self.cache_non_get;
+
+        Box::pin(async move {
+            let (client, request) = req
+                .try_clone()
+                .ok_or_else(|| {
+                    ScrapeError::RequestCloneError("cannot clone a streaming request body".to_string())
+                })?
+                .build_split();
+            let request = request.map_err(ScrapeError::Http)?;
+
+            let cacheable = request.method() == reqwest::Method::GET || cache_non_get;
+            let base_key = cacheable.then(|| CacheKey::from_request(&request));
+
+            if let Some(base_key) = &base_key {
+                let key = store.resolve_key(base_key, request.headers());
+                if let Some(entry) = store.load(&key) {
+                    if entry.is_fresh() {
+                        return entry.into_response(request.url().clone());
+                    }
+                    let body = request.body().and_then(|b| b.as_bytes()).map(|b| b.to_vec());
+                    if let Some(conditional) = entry.conditional_request(
+                        &client,
+                        request.method().clone(),
+                        request.url().clone(),
+                        body,
+                    ) {
+                        let resp = inner.call(conditional).await.map_err(ScrapeError::Http)?;
+                        if resp.status() == reqwest::StatusCode::NOT_MODIFIED {
+                            let refreshed = entry.refreshed(&resp);
+                            store.store(&key, &refreshed);
+                            return refreshed.into_response(request.url().clone());
+                        }
+                        return store.insert_response(base_key, request.headers(), resp, cacheable).await;
+                    }
+                }
+            }
+
+            let resp = inner.call(req).await.map_err(ScrapeError::Http)?;
+            if let Some(base_key) = &base_key {
+                return store.insert_response(base_key, request.headers(), resp, cacheable).await;
+            }
+            Ok(resp)
+        })
+    }
+}
+
+/// Identifies a cached entry: method + URL, folding in a hash of the form
+/// body for POSTs and, once the response is known to vary, a hash of the
+/// request headers it varies on (see `Store::resolve_key`).
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+struct CacheKey {
+    method: String,
+    url: String,
+    body_hash: Option<u64>,
+    vary_hash: Option<u64>,
+}
+
+impl CacheKey {
+    fn from_request(request: &reqwest::Request) -> Self {
+        let body_hash = request.body().and_then(|b| b.as_bytes()).map(|bytes| {
+            let mut hasher = DefaultHasher::new();
+            bytes.hash(&mut hasher);
+            hasher.finish()
+        });
+        Self {
+            method: request.method().to_string(),
+            url: request.url().as_str().to_string(),
+            body_hash,
+            vary_hash: None,
+        }
+    }
+
+    /// Fold the given `vary` header names into the key, hashing their
+    /// values out of `request_headers`. `Vary` names *request* headers
+    /// (e.g. `Accept-Encoding`), so this must always be called with the
+    /// request's headers, never the response's.
+    fn with_vary(mut self, request_headers: &header::HeaderMap, vary: &str) -> Self {
+        let mut hasher = DefaultHasher::new();
+        for name in vary.split(',').map(|s| s.trim()) {
+            if let Some(value) = request_headers.get(name) {
+                value.as_bytes().hash(&mut hasher);
+            }
+        }
+        self.vary_hash = Some(hasher.finish());
+        self
+    }
+
+    fn filename(&self) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+}
+
+/// Headers excluded when an `Entry` captures a response: `Content-Length`
+/// is recomputed from the cached body, and `Set-Cookie` must never be
+/// replayed from cache (responses that set cookies aren't cached at all,
+/// but this keeps `refreshed`'s 304-driven header merge just as safe).
+const UNCACHED_HEADERS: [header::HeaderName; 2] = [header::CONTENT_LENGTH, header::SET_COOKIE];
+
+/// Everything needed to either serve a cached response or revalidate it.
+///
+/// Captures every response header (bar `UNCACHED_HEADERS`) rather than just
+/// `ETag`/`Last-Modified`, so a cache hit still carries e.g. `Content-Type`
+/// — losing it would make `mime::declared_charset` silently fall back to
+/// UTF-8 for a resumed scrape's second-and-later runs.
+#[derive(Debug, Clone)]
+struct Entry {
+    status: u16,
+    headers: Vec<(String, String)>,
+    stored_at: Duration,
+    freshness: Duration,
+    body: Vec<u8>,
+}
+
+impl Entry {
+    fn from_response(status: reqwest::StatusCode, headers: &header::HeaderMap, body: Vec<u8>) -> Self {
+        Self {
+            status: status.as_u16(),
+            headers: captured_headers(headers),
+            stored_at: now(),
+            freshness: freshness_lifetime(headers),
+            body,
+        }
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+    }
+
+    fn is_fresh(&self) -> bool {
+        now().saturating_sub(self.stored_at) < self.freshness
+    }
+
+    /// Build a revalidation request that repeats the original method and
+    /// body (important for the Bundestag's cached POST search pages, where
+    /// a bare `GET` to the same URL would hit a different endpoint).
+    fn conditional_request(
+        &self,
+        client: &reqwest::Client,
+        method: reqwest::Method,
+        url: url::Url,
+        body: Option<Vec<u8>>,
+    ) -> Option<RequestBuilder> {
+        let etag = self.header("etag");
+        let last_modified = self.header("last-modified");
+        if etag.is_none() && last_modified.is_none() {
+            return None;
+        }
+        let mut builder = client.request(method, url);
+        if let Some(body) = body {
+            builder = builder.body(body);
+        }
+        if let Some(etag) = etag {
+            builder = builder.header(header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = last_modified {
+            builder = builder.header(header::IF_MODIFIED_SINCE, last_modified);
+        }
+        Some(builder)
+    }
+
+    /// Merge in whatever headers a `304 Not Modified` carried (a server may
+    /// refresh `Cache-Control`/`Expires`/`ETag` without resending the body),
+    /// keeping everything else from the original entry.
+    fn refreshed(&self, resp: &Response) -> Self {
+        let mut headers = self.headers.clone();
+        for (name, value) in captured_headers(resp.headers()) {
+            match headers.iter_mut().find(|entry| entry.0.eq_ignore_ascii_case(&name)) {
+                Some(existing) => existing.1 = value,
+                None => headers.push((name, value)),
+            }
+        }
+        Self {
+            status: self.status,
+            headers,
+            stored_at: now(),
+            freshness: freshness_lifetime(resp.headers()),
+            body: self.body.clone(),
+        }
+    }
+
+    fn into_response(&self, url: url::Url) -> Result<Response, ScrapeError> {
+        let mut builder = http::Response::builder().status(self.status);
+        for (name, value) in &self.headers {
+            builder = builder.header(name, value);
+        }
+        let http_resp = builder
+            .body(self.body.clone())
+            .map_err(|e| ScrapeError::Parse(format!("{:}", e)))?;
+        let (mut parts, body) = http_resp.into_parts();
+        parts.extensions.insert(url);
+        Ok(Response::from(http::Response::from_parts(parts, body)))
+    }
+}
+
+fn header_str(headers: &header::HeaderMap, name: header::HeaderName) -> Option<String> {
+    headers.get(name)?.to_str().ok().map(str::to_string)
+}
+
+/// Snapshot every textual header off `headers`, skipping `UNCACHED_HEADERS`.
+fn captured_headers(headers: &header::HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter(|entry| !UNCACHED_HEADERS.contains(entry.0))
+        .filter_map(|(name, value)| value.to_str().ok().map(|v| (name.to_string(), v.to_string())))
+        .collect()
+}
+
+/// Compute how long a response stays fresh, preferring `Cache-Control:
+/// max-age` over `Expires`, and never caching when `no-store` is present.
+fn freshness_lifetime(headers: &header::HeaderMap) -> Duration {
+    if let Some(cache_control) = header_str(headers, header::CACHE_CONTROL) {
+        let directives: Vec<&str> = cache_control.split(',').map(|s| s.trim()).collect();
+        if directives.iter().any(|d| d.eq_ignore_ascii_case("no-store")) {
+            return Duration::from_secs(0);
+        }
+        for directive in &directives {
+            if let Some(secs) = directive
+                .strip_prefix("max-age=")
+                .or_else(|| directive.strip_prefix("s-maxage="))
+            {
+                if let Ok(secs) = secs.parse::<u64>() {
+                    return Duration::from_secs(secs);
+                }
+            }
+        }
+    }
+    if let Some(expires) = header_str(headers, header::EXPIRES) {
+        if let Ok(at) = httpdate::parse_http_date(&expires) {
+            if let Ok(lifetime) = at.duration_since(SystemTime::now()) {
+                return lifetime;
+            }
+        }
+    }
+    DEFAULT_FRESHNESS
+}
+
+fn now() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+/// On-disk storage for cache entries: one `.meta` file (headers/timestamps)
+/// and one `.body` file per key, so a scrape survives a restart.
+#[derive(Clone, Debug)]
+struct Store {
+    dir: PathBuf,
+}
+
+impl Store {
+    fn new(dir: &Path) -> Self {
+        let _ = fs::create_dir_all(dir);
+        Self { dir: dir.to_path_buf() }
+    }
+
+    fn meta_path(&self, key: &CacheKey) -> PathBuf {
+        self.dir.join(format!("{}.meta", key.filename()))
+    }
+
+    fn body_path(&self, key: &CacheKey) -> PathBuf {
+        self.dir.join(format!("{}.body", key.filename()))
+    }
+
+    fn vary_path(&self, base_key: &CacheKey) -> PathBuf {
+        self.dir.join(format!("{}.vary", base_key.filename()))
+    }
+
+    /// Resolve `base_key` (method + URL + body hash, no `Vary` folded in
+    /// yet) to the key actually used on disk, consulting the `Vary` header
+    /// names recorded by a previous `insert_response` for this resource, if
+    /// any. This is what lets a lookup made *before* the response (and thus
+    /// its `Vary`) is known land on the same key a matching store used.
+    fn resolve_key(&self, base_key: &CacheKey, request_headers: &header::HeaderMap) -> CacheKey {
+        match fs::read_to_string(self.vary_path(base_key)) {
+            Ok(vary) => base_key.clone().with_vary(request_headers, &vary),
+            Err(_) => base_key.clone(),
+        }
+    }
+
+    fn store_vary(&self, base_key: &CacheKey, vary: &str) {
+        let _ = fs::write(self.vary_path(base_key), vary);
+    }
+
+    fn load(&self, key: &CacheKey) -> Option<Entry> {
+        let meta = fs::read_to_string(self.meta_path(key)).ok()?;
+        let body = fs::read(self.body_path(key)).ok()?;
+        let mut status = None;
+        let mut stored_at = None;
+        let mut freshness = None;
+        let mut headers = vec![];
+        for line in meta.lines() {
+            let (field, value) = line.split_once('=')?;
+            match field {
+                "status" => status = value.parse().ok(),
+                "stored_at" => stored_at = value.parse().ok().map(Duration::from_secs),
+                "freshness" => freshness = value.parse().ok().map(Duration::from_secs),
+                _ => {
+                    if let Some(name) = field.strip_prefix("header.") {
+                        headers.push((name.to_string(), value.to_string()));
+                    }
+                }
+            }
+        }
+        Some(Entry {
+            status: status?,
+            headers,
+            stored_at: stored_at?,
+            freshness: freshness?,
+            body,
+        })
+    }
+
+    fn store(&self, key: &CacheKey, entry: &Entry) {
+        let mut meta = String::new();
+        meta.push_str(&format!("status={}\n", entry.status));
+        meta.push_str(&format!("stored_at={}\n", entry.stored_at.as_secs()));
+        meta.push_str(&format!("freshness={}\n", entry.freshness.as_secs()));
+        for (name, value) in &entry.headers {
+            meta.push_str(&format!("header.{}={}\n", name, value));
+        }
+        let _ = fs::write(self.meta_path(key), meta);
+        let _ = fs::write(self.body_path(key), &entry.body);
+    }
+
+    /// Fetch the full body of `resp`, store it as a fresh entry (unless it
+    /// sets cookies, which must never be replayed from cache), and return a
+    /// response equivalent to the one the caller would have gotten live.
+    ///
+    /// `base_key` is the method/URL/body-hash key computed before the
+    /// request was sent; `request_headers` are that same request's headers,
+    /// used (not the response's) to resolve any `Vary` the response declares.
+    async fn insert_response(
+        &self,
+        base_key: &CacheKey,
+        request_headers: &header::HeaderMap,
+        resp: Response,
+        cacheable: bool,
+    ) -> Result<Response, ScrapeError> {
+        let url = resp.url().clone();
+        let status = resp.status();
+        let headers = resp.headers().clone();
+        let body = resp.bytes().await.map_err(ScrapeError::Http)?.to_vec();
+
+        let sets_cookie = headers.contains_key(header::SET_COOKIE);
+        let vary = headers.get(header::VARY).and_then(|v| v.to_str().ok());
+        let key = vary
+            .map(|vary| base_key.clone().with_vary(request_headers, vary))
+            .unwrap_or_else(|| base_key.clone());
+
+        let no_store = header_str(&headers, header::CACHE_CONTROL).map_or(false, |cc| {
+            cc.split(',').any(|d| d.trim().eq_ignore_ascii_case("no-store"))
+        });
+
+        if cacheable && !sets_cookie && !no_store && status.is_success() {
+            if let Some(vary) = vary {
+                self.store_vary(base_key, vary);
+            }
+            let entry = Entry::from_response(status, &headers, body.clone());
+            self.store(&key, &entry);
+        }
+
+        let mut builder = http::Response::builder().status(status);
+        for (name, value) in headers.iter() {
+            builder = builder.header(name, value);
+        }
+        let http_resp = builder
+            .body(body)
+            .map_err(|e| ScrapeError::Parse(format!("{:}", e)))?;
+        let (mut parts, body) = http_resp.into_parts();
+        parts.extensions.insert(url);
+        Ok(Response::from(http::Response::from_parts(parts, body)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    fn temp_store() -> Store {
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let dir = std::env::temp_dir().join(format!("scraping-dbg-cache-test-{}-{}", std::process::id(), n));
+        Store::new(&dir)
+    }
+
+    fn base_key(url: &str) -> CacheKey {
+        CacheKey {
+            method: "GET".to_string(),
+            url: url.to_string(),
+            body_hash: None,
+            vary_hash: None,
+        }
+    }
+
+    fn entry(body: &[u8]) -> Entry {
+        Entry {
+            status: 200,
+            headers: vec![("content-type".to_string(), "text/html".to_string())],
+            stored_at: now(),
+            freshness: Duration::from_secs(60),
+            body: body.to_vec(),
+        }
+    }
+
+    #[test]
+    fn store_then_load_round_trips() {
+        let store = temp_store();
+        let key = base_key("http://example.com/");
+        let original = entry(b"hello");
+        store.store(&key, &original);
+
+        let loaded = store.load(&key).expect("entry should round-trip");
+        assert_eq!(loaded.status, original.status);
+        assert_eq!(loaded.body, original.body);
+        assert_eq!(loaded.header("content-type"), Some("text/html"));
+    }
+
+    #[test]
+    fn into_response_restores_headers_lost_by_a_naive_entry() {
+        let resp = entry(b"<html></html>")
+            .into_response(url::Url::parse("http://example.com/").unwrap())
+            .unwrap();
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok()),
+            Some("text/html")
+        );
+    }
+
+    #[test]
+    fn resolve_key_without_a_stored_vary_is_just_the_base_key() {
+        let store = temp_store();
+        let base = base_key("http://example.com/a");
+        assert_eq!(store.resolve_key(&base, &header::HeaderMap::new()), base);
+    }
+
+    #[test]
+    fn resolve_key_matches_the_key_a_varying_store_used() {
+        let store = temp_store();
+        let base = base_key("http://example.com/a");
+
+        let mut request_headers = header::HeaderMap::new();
+        request_headers.insert(header::ACCEPT_ENCODING, "gzip".parse().unwrap());
+
+        store.store_vary(&base, "Accept-Encoding");
+        let stored_key = base.clone().with_vary(&request_headers, "Accept-Encoding");
+
+        assert_eq!(store.resolve_key(&base, &request_headers), stored_key);
+    }
+
+    #[test]
+    fn with_vary_distinguishes_different_request_header_values() {
+        let base = base_key("http://example.com/a");
+
+        let mut gzip = header::HeaderMap::new();
+        gzip.insert(header::ACCEPT_ENCODING, "gzip".parse().unwrap());
+        let mut br = header::HeaderMap::new();
+        br.insert(header::ACCEPT_ENCODING, "br".parse().unwrap());
+
+        let gzip_key = base.clone().with_vary(&gzip, "Accept-Encoding");
+        let br_key = base.clone().with_vary(&br, "Accept-Encoding");
+        assert_ne!(gzip_key, br_key);
+    }
+
+    #[test]
+    fn freshness_lifetime_prefers_max_age_over_expires() {
+        let mut headers = header::HeaderMap::new();
+        headers.insert(header::CACHE_CONTROL, "max-age=120".parse().unwrap());
+        let expires = SystemTime::now() + Duration::from_secs(3600);
+        headers.insert(header::EXPIRES, httpdate::fmt_http_date(expires).parse().unwrap());
+        assert_eq!(freshness_lifetime(&headers), Duration::from_secs(120));
+    }
+
+    #[test]
+    fn freshness_lifetime_is_zero_on_no_store() {
+        let mut headers = header::HeaderMap::new();
+        headers.insert(header::CACHE_CONTROL, "no-store, max-age=120".parse().unwrap());
+        assert_eq!(freshness_lifetime(&headers), Duration::from_secs(0));
+    }
+}
@@ -0,0 +1,152 @@
+//! A bounded-concurrency fetch stage with overload shedding.
+//!
+//! Plain `buffer_unordered` already caps how many fetches run at once, but
+//! a burst of discovered links still queues up unboundedly behind it. This
+//! adds a bounded waiting queue in front: once it's full, a random pending
+//! (not yet started) request is shed in favour of the new one, so the
+//! pipeline degrades gracefully instead of growing memory without bound or
+//! deadlocking.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use rand::Rng;
+use tokio::sync::{Mutex, Notify, Semaphore};
+
+/// Outcome of submitting a fetch through a [`FetchQueue`].
+#[derive(Debug)]
+pub enum FetchOutcome<T> {
+    Completed(T),
+    /// This request was still waiting for a slot when the queue filled up,
+    /// and was shed to make room for a newer one.
+    Overloaded,
+}
+
+pub struct FetchQueueBuilder {
+    concurrency: usize,
+    queue_size: usize,
+}
+
+impl Default for FetchQueueBuilder {
+    fn default() -> Self {
+        Self {
+            concurrency: std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4),
+            queue_size: 128,
+        }
+    }
+}
+
+impl FetchQueueBuilder {
+    /// How many fetches may run at once.
+    pub fn concurrency(mut self, n: usize) -> Self {
+        self.concurrency = n.max(1);
+        self
+    }
+
+    /// How many more fetches may wait for a slot before shedding kicks in.
+    pub fn queue_size(mut self, n: usize) -> Self {
+        self.queue_size = n;
+        self
+    }
+
+    pub fn build(self) -> FetchQueue {
+        FetchQueue {
+            semaphore: Arc::new(Semaphore::new(self.concurrency)),
+            pending: Arc::new(Mutex::new(Vec::new())),
+            queue_size: self.queue_size,
+        }
+    }
+}
+
+pub struct FetchQueue {
+    semaphore: Arc<Semaphore>,
+    pending: Arc<Mutex<Vec<Arc<Notify>>>>,
+    queue_size: usize,
+}
+
+impl FetchQueue {
+    pub fn builder() -> FetchQueueBuilder {
+        FetchQueueBuilder::default()
+    }
+
+    /// Wait for a slot and run `make_fetch`, or shed load if the queue is
+    /// already full. `make_fetch` is only invoked once a slot is granted,
+    /// so a shed request never actually hits the network.
+    pub async fn run<F, Fut, T>(&self, make_fetch: F) -> FetchOutcome<T>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = T>,
+    {
+        let my_slot = Arc::new(Notify::new());
+        {
+            let mut pending = self.pending.lock().await;
+            if pending.len() >= self.queue_size {
+                let victim = rand::thread_rng().gen_range(0..pending.len());
+                pending.remove(victim).notify_one();
+            }
+            pending.push(my_slot.clone());
+        }
+
+        let permit = tokio::select! {
+            biased;
+            _ = my_slot.notified() => None,
+            permit = self.semaphore.clone().acquire_owned() => permit.ok(),
+        };
+
+        {
+            let mut pending = self.pending.lock().await;
+            pending.retain(|slot| !Arc::ptr_eq(slot, &my_slot));
+        }
+
+        match permit {
+            Some(_permit) => FetchOutcome::Completed(make_fetch().await),
+            None => FetchOutcome::Overloaded,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn runs_a_fetch_when_a_slot_is_free() {
+        let queue = FetchQueue::builder().concurrency(4).queue_size(4).build();
+        let outcome = queue.run(|| async { 42 }).await;
+        assert!(matches!(outcome, FetchOutcome::Completed(42)));
+    }
+
+    #[tokio::test]
+    async fn sheds_the_pending_request_once_the_queue_is_full() {
+        // Concurrency of 1 with a queue size of 1: once the only permit is
+        // held and one request is already waiting for it, a second arrival
+        // must shed that waiter instead of growing the queue.
+        let queue = Arc::new(FetchQueue::builder().concurrency(1).queue_size(1).build());
+        let gate = Arc::new(Notify::new());
+
+        let h_gate = gate.clone();
+        let h_queue = queue.clone();
+        let holder = tokio::spawn(async move { h_queue.run(|| async move { h_gate.notified().await }).await });
+        for _ in 0..4 {
+            tokio::task::yield_now().await;
+        }
+
+        let a_queue = queue.clone();
+        let waiting = tokio::spawn(async move { a_queue.run(|| async { "a" }).await });
+        for _ in 0..4 {
+            tokio::task::yield_now().await;
+        }
+
+        let b_queue = queue.clone();
+        let arriving = tokio::spawn(async move { b_queue.run(|| async { "b" }).await });
+        for _ in 0..4 {
+            tokio::task::yield_now().await;
+        }
+
+        assert!(matches!(waiting.await.unwrap(), FetchOutcome::Overloaded));
+
+        gate.notify_one();
+        assert!(matches!(holder.await.unwrap(), FetchOutcome::Completed(())));
+        assert!(matches!(arriving.await.unwrap(), FetchOutcome::Completed("b")));
+    }
+}
@@ -0,0 +1,25 @@
+use crate::scrapers::selector::Selector;
+use crate::scrapers::ScrapeError;
+
+/// Types that can be built out of a parsed [`Selector`] by pulling fields
+/// out via XPath.
+///
+/// Usually derived with `#[derive(FromSelector)]`:
+///
+/// ```ignore
+/// #[derive(FromSelector)]
+/// struct BipData {
+///     #[xpath("//fieldset[h1[contains(text(), 'Basisinformationen')]]")]
+///     summary: String,
+///     #[xpath("//fieldset[h1[contains(text(), 'Inhalt')]]", optional)]
+///     content: Option<String>,
+/// }
+/// ```
+///
+/// A bare field type is required and errors with
+/// `ScrapeError::MissingField` naming the field when no node matches
+/// (mirroring `parsing::ParsingError::MissingField`); an `Option<T>` maps a
+/// missing node to `None`; a `Vec<T>` collects every matching node.
+pub trait FromSelector: Sized {
+    fn from_selector(selector: &Selector) -> Result<Self, ScrapeError>;
+}
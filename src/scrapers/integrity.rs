@@ -0,0 +1,156 @@
+//! `SubresourceIntegrity`-style digest verification for archived documents.
+//!
+//! Lets callers pin a URL to a known checksum and refuse to pass a mismatch
+//! downstream, which matters when `detail_views`/`pipe_out` are building a
+//! reproducible archive of legal gazette pages.
+
+use std::collections::HashMap;
+
+use base64::Engine as _;
+use futures::stream::{Stream, StreamExt};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use url::Url;
+
+use crate::scrapers::ScrapeError;
+
+/// Supported digest algorithms, ordered weakest to strongest so the
+/// strongest of several tokens can be picked with `Ord`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Algorithm {
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl Algorithm {
+    fn digest(self, body: &[u8]) -> Vec<u8> {
+        match self {
+            Algorithm::Sha256 => Sha256::digest(body).to_vec(),
+            Algorithm::Sha384 => Sha384::digest(body).to_vec(),
+            Algorithm::Sha512 => Sha512::digest(body).to_vec(),
+        }
+    }
+}
+
+/// A parsed integrity metadata value: one or more whitespace-separated
+/// `alg-base64digest` tokens (e.g. `sha256-abcd...`). When several are
+/// present, only the strongest supported algorithm is kept.
+#[derive(Debug, Clone)]
+pub struct SubresourceIntegrity {
+    algorithm: Algorithm,
+    digest: Vec<u8>,
+}
+
+impl SubresourceIntegrity {
+    pub fn parse(value: &str) -> Result<Self, ScrapeError> {
+        value
+            .split_whitespace()
+            .filter_map(parse_token)
+            .max_by_key(|(algorithm, _)| *algorithm)
+            .map(|(algorithm, digest)| Self { algorithm, digest })
+            .ok_or_else(|| ScrapeError::Parse(format!("No supported integrity token in {:?}", value)))
+    }
+
+    /// Compare the digest of the raw (pre-decode) response bytes against
+    /// the expected value in constant time.
+    pub fn verify(&self, body: &[u8]) -> bool {
+        constant_time_eq(&self.algorithm.digest(body), &self.digest)
+    }
+}
+
+fn parse_token(token: &str) -> Option<(Algorithm, Vec<u8>)> {
+    let (alg, encoded) = token.split_once('-')?;
+    let algorithm = match alg {
+        "sha256" => Algorithm::Sha256,
+        "sha384" => Algorithm::Sha384,
+        "sha512" => Algorithm::Sha512,
+        _ => return None,
+    };
+    let digest = base64::engine::general_purpose::STANDARD.decode(encoded).ok()?;
+    Some((algorithm, digest))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Verify `body` against `expected[url]`. A URL with no expected digest
+/// passes through unchanged; a present-but-mismatched digest is a hard
+/// error.
+pub fn verify_body(
+    url: &Url,
+    body: Vec<u8>,
+    expected: &HashMap<Url, SubresourceIntegrity>,
+) -> Result<Vec<u8>, ScrapeError> {
+    match expected.get(url) {
+        Some(sri) if !sri.verify(&body) => Err(ScrapeError::IntegrityMismatch(url.to_string())),
+        _ => Ok(body),
+    }
+}
+
+/// Wrap a stream of fetched `(url, body)` pairs so a digest mismatch
+/// surfaces as an error instead of silently reaching
+/// `StdOutPipeline::handle_item`.
+pub fn verify_stream<'a, S>(
+    items: S,
+    expected: &'a HashMap<Url, SubresourceIntegrity>,
+) -> impl Stream<Item = Result<(Url, Vec<u8>), ScrapeError>> + 'a
+where
+    S: Stream<Item = Result<(Url, Vec<u8>), ScrapeError>> + 'a,
+{
+    items.map(move |item| {
+        let (url, body) = item?;
+        let body = verify_body(&url, body, expected)?;
+        Ok((url, body))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_verify_a_matching_sha256_digest() {
+        let digest = base64::engine::general_purpose::STANDARD.encode(Sha256::digest(b"hello"));
+        let sri = SubresourceIntegrity::parse(&format!("sha256-{}", digest)).unwrap();
+        assert!(sri.verify(b"hello"));
+        assert!(!sri.verify(b"goodbye"));
+    }
+
+    #[test]
+    fn parse_picks_the_strongest_of_several_tokens() {
+        let sha256 = base64::engine::general_purpose::STANDARD.encode(Sha256::digest(b"hello"));
+        let sha512 = base64::engine::general_purpose::STANDARD.encode(Sha512::digest(b"hello"));
+        let sri =
+            SubresourceIntegrity::parse(&format!("sha256-{} sha512-{}", sha256, sha512)).unwrap();
+        assert!(sri.verify(b"hello"));
+        assert_eq!(sri.algorithm, Algorithm::Sha512);
+    }
+
+    #[test]
+    fn parse_rejects_unsupported_algorithms() {
+        let digest = base64::engine::general_purpose::STANDARD.encode(b"whatever");
+        assert!(SubresourceIntegrity::parse(&format!("md5-{}", digest)).is_err());
+    }
+
+    #[test]
+    fn verify_body_passes_urls_with_no_expected_digest() {
+        let url = Url::parse("https://example.com/a.pdf").unwrap();
+        let expected = HashMap::new();
+        assert_eq!(verify_body(&url, b"anything".to_vec(), &expected).unwrap(), b"anything");
+    }
+
+    #[test]
+    fn verify_body_rejects_a_mismatched_digest() {
+        let url = Url::parse("https://example.com/a.pdf").unwrap();
+        let digest = base64::engine::general_purpose::STANDARD.encode(Sha256::digest(b"expected"));
+        let mut expected = HashMap::new();
+        expected.insert(url.clone(), SubresourceIntegrity::parse(&format!("sha256-{}", digest)).unwrap());
+
+        let err = verify_body(&url, b"actual".to_vec(), &expected).unwrap_err();
+        assert!(matches!(err, ScrapeError::IntegrityMismatch(_)));
+    }
+}
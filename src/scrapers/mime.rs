@@ -0,0 +1,150 @@
+use reqwest::header::{self, HeaderMap};
+
+/// Coarse classification of a response body, used to pick the right parser
+/// instead of always feeding bytes to the HTML parser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mime {
+    Html,
+    Xml,
+    Json,
+    Pdf,
+    Zip,
+    Jpeg,
+    Png,
+    Gif,
+    /// Anything we recognised neither from the header nor the magic bytes.
+    Unknown,
+}
+
+impl Mime {
+    /// Classify a response: trust a specific `Content-Type` header first,
+    /// falling back to sniffing the leading bytes when it is missing or
+    /// one of the generic catch-all values.
+    pub fn detect(headers: &HeaderMap, body: &[u8]) -> Self {
+        if let Some(declared) = declared_mime(headers) {
+            return declared;
+        }
+        sniff(body)
+    }
+
+    /// Whether this mime type should be handed to the HTML/XML parser.
+    pub fn is_markup(self) -> bool {
+        matches!(self, Mime::Html | Mime::Xml)
+    }
+}
+
+fn declared_mime(headers: &HeaderMap) -> Option<Mime> {
+    let content_type = headers.get(header::CONTENT_TYPE)?.to_str().ok()?;
+    let essence = content_type.split(';').next()?.trim().to_ascii_lowercase();
+    match essence.as_str() {
+        "application/octet-stream" | "text/plain" | "" => None,
+        "text/html" | "application/xhtml+xml" => Some(Mime::Html),
+        "text/xml" | "application/xml" => Some(Mime::Xml),
+        "application/json" | "text/json" => Some(Mime::Json),
+        "application/pdf" => Some(Mime::Pdf),
+        "application/zip"
+        | "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => {
+            Some(Mime::Zip)
+        }
+        "image/jpeg" => Some(Mime::Jpeg),
+        "image/png" => Some(Mime::Png),
+        "image/gif" => Some(Mime::Gif),
+        _ => None,
+    }
+}
+
+/// Extract a declared charset (e.g. `ISO-8859-15`) from the `Content-Type`
+/// header, if any. The Bundestag pages declare this.
+pub fn declared_charset(headers: &HeaderMap) -> Option<String> {
+    let content_type = headers.get(header::CONTENT_TYPE)?.to_str().ok()?;
+    content_type
+        .split(';')
+        .skip(1)
+        .find_map(|part| part.trim().strip_prefix("charset="))
+        .map(|charset| charset.trim_matches('"').to_string())
+}
+
+/// Match well-known magic signatures in the first ~512 bytes of the body.
+fn sniff(body: &[u8]) -> Mime {
+    let head = &body[..body.len().min(512)];
+    let trimmed = trim_leading_ascii_whitespace(head);
+
+    if trimmed.starts_with(b"%PDF-") {
+        return Mime::Pdf;
+    }
+    if trimmed.starts_with(b"PK\x03\x04") {
+        return Mime::Zip;
+    }
+    if trimmed.starts_with(b"\xFF\xD8\xFF") {
+        return Mime::Jpeg;
+    }
+    if trimmed.starts_with(b"\x89PNG") {
+        return Mime::Png;
+    }
+    if trimmed.starts_with(b"GIF8") {
+        return Mime::Gif;
+    }
+    if trimmed.starts_with(b"<?xml") {
+        return Mime::Xml;
+    }
+    if trimmed.starts_with(b"<") {
+        return Mime::Html;
+    }
+    if (trimmed.starts_with(b"{") || trimmed.starts_with(b"[")) && std::str::from_utf8(trimmed).is_ok()
+    {
+        return Mime::Json;
+    }
+    Mime::Unknown
+}
+
+fn trim_leading_ascii_whitespace(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn declared_content_type_wins_over_sniffing() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, "application/pdf".parse().unwrap());
+        assert_eq!(Mime::detect(&headers, b"not actually a pdf"), Mime::Pdf);
+    }
+
+    #[test]
+    fn generic_content_type_falls_back_to_sniffing() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, "application/octet-stream".parse().unwrap());
+        assert_eq!(Mime::detect(&headers, b"%PDF-1.7"), Mime::Pdf);
+    }
+
+    #[test]
+    fn sniffs_known_magic_bytes() {
+        let headers = HeaderMap::new();
+        assert_eq!(Mime::detect(&headers, b"%PDF-1.4"), Mime::Pdf);
+        assert_eq!(Mime::detect(&headers, b"PK\x03\x04rest"), Mime::Zip);
+        assert_eq!(Mime::detect(&headers, b"\xFF\xD8\xFFrest"), Mime::Jpeg);
+        assert_eq!(Mime::detect(&headers, b"\x89PNGrest"), Mime::Png);
+        assert_eq!(Mime::detect(&headers, b"GIF89arest"), Mime::Gif);
+        assert_eq!(Mime::detect(&headers, b"<?xml version=\"1.0\"?>"), Mime::Xml);
+        assert_eq!(Mime::detect(&headers, b"  \n<html></html>"), Mime::Html);
+        assert_eq!(Mime::detect(&headers, b"{\"a\": 1}"), Mime::Json);
+        assert_eq!(Mime::detect(&headers, b"who knows"), Mime::Unknown);
+    }
+
+    #[test]
+    fn declared_charset_is_parsed_out_of_content_type() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, "text/html; charset=ISO-8859-15".parse().unwrap());
+        assert_eq!(declared_charset(&headers).as_deref(), Some("ISO-8859-15"));
+    }
+
+    #[test]
+    fn declared_charset_is_none_when_absent() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, "text/html".parse().unwrap());
+        assert_eq!(declared_charset(&headers), None);
+    }
+}
@@ -1,23 +1,19 @@
 use std::time::Duration;
 
-use thiserror::Error;
 use reqwest::{self, header, Client, ClientBuilder};
 
+pub mod cache;
+pub mod concurrency;
+pub mod from_selector;
+pub mod integrity;
+pub mod mime;
 pub mod retry;
 pub mod selector;
 pub mod pipelines;
 
-#[derive(Debug, Error)]
-pub enum EngineError {
-    #[error("Parsing Error")]
-    ParsingError(String),
-    #[error("Network Error")]
-    IoError(#[from] reqwest::Error),
-    #[error("Requests where the body is a Stream cannot be clones")]
-    RequestCloneError(String),
-}
+pub use crate::error::ScrapeError;
 
-pub fn default_client() -> Result<Client, EngineError> {
+pub fn default_client() -> Result<Client, ScrapeError> {
     let mut headers = header::HeaderMap::new();
     headers.insert(
         header::USER_AGENT,
@@ -1,28 +1,48 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::time::{Duration, SystemTime};
+
+use rand::Rng;
+use reqwest::{header, Error, RequestBuilder, Response, StatusCode};
 use tower::retry::Policy;
-use futures::future;
-use reqwest::{RequestBuilder, Response, Error};
+
+/// Sleep applied on a `429`/`503` with no (or an unparsable) `Retry-After`.
+const DEFAULT_RETRY_DURATION: Duration = Duration::from_secs(10);
 
 #[derive(Clone, Debug)]
 pub struct RetryLimit {
     remaining_tries: usize,
+    max_tries: usize,
+    /// Base delay for the exponential backoff applied to transport errors.
+    base: Duration,
+    /// Upper bound on the (pre-jitter) backoff delay.
+    cap: Duration,
 }
 
 impl Policy<RequestBuilder, Response, Error> for RetryLimit {
-    type Future = future::Ready<Self>;
+    type Future = Pin<Box<dyn Future<Output = Self> + Send>>;
 
     fn retry(&self, _req: &RequestBuilder, result: Result<&Response, &Error>) -> Option<Self::Future> {
-        match result {
-            Ok(resp) => {
-		match resp.error_for_status_ref() {
-		    Ok(_resp) => None,
-		    Err(_e) => self.should_retry()
-		}
-            },
-            Err(_) => {
-                // We should probably just give up but lets keep trying even in this case.
-		self.should_retry()
+        let (next, delay) = match result {
+            Ok(resp) if resp.status().is_success() => return None,
+            Ok(resp) if is_retryable_status(resp.status()) => {
+                let next = self.should_retry()?;
+                let delay = retry_after(resp.headers()).unwrap_or(DEFAULT_RETRY_DURATION);
+                (next, delay)
             }
-        }
+            // A genuine 404/403/redirect loop won't get any better on a
+            // retry, so give up at once instead of burning all tries.
+            Ok(_resp) => return None,
+            Err(e) if is_retryable_error(e) => {
+                let next = self.should_retry()?;
+                (next, self.backoff())
+            }
+            Err(_e) => return None,
+        };
+        Some(Box::pin(async move {
+            tokio::time::sleep(delay).await;
+            next
+        }))
     }
 
     fn clone_request(&self, req: &RequestBuilder) -> Option<RequestBuilder> {
@@ -32,15 +52,139 @@ impl Policy<RequestBuilder, Response, Error> for RetryLimit {
 
 impl RetryLimit {
     pub fn new(remaining_tries: usize) -> Self {
-	Self { remaining_tries }
+        Self {
+            remaining_tries,
+            max_tries: remaining_tries,
+            base: Duration::from_millis(500),
+            cap: Duration::from_secs(30),
+        }
     }
-    
-    fn should_retry(&self) -> Option<future::Ready<Self>> {
-	let remaining_tries = self.remaining_tries - 1;
-	if self.remaining_tries > 0 {
-            Some(future::ready(RetryLimit{ remaining_tries }))
-        } else {
+
+    fn should_retry(&self) -> Option<RetryLimit> {
+        if self.remaining_tries == 0 {
             None
+        } else {
+            Some(RetryLimit { remaining_tries: self.remaining_tries - 1, ..*self })
+        }
+    }
+
+    /// Exponential backoff from the current attempt number, capped and
+    /// jittered by ±50% to avoid a thundering herd across the
+    /// rate-limited stream.
+    fn backoff(&self) -> Duration {
+        let attempt = self.max_tries - self.remaining_tries;
+        let exp = self.base.saturating_mul(1u32.checked_shl(attempt as u32).unwrap_or(u32::MAX));
+        jitter(exp.min(self.cap))
+    }
+}
+
+fn jitter(delay: Duration) -> Duration {
+    let factor = rand::thread_rng().gen_range(0.5..1.5);
+    Duration::from_secs_f64(delay.as_secs_f64() * factor)
+}
+
+/// Parse a `Retry-After` header in either its integer-seconds or HTTP-date
+/// form.
+fn retry_after(headers: &header::HeaderMap) -> Option<Duration> {
+    let value = headers.get(header::RETRY_AFTER)?.to_str().ok()?;
+    if let Ok(secs) = value.trim().parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = httpdate::parse_http_date(value.trim()).ok()?;
+    at.duration_since(SystemTime::now()).ok()
+}
+
+/// Only these statuses are intermittent enough to be worth a retry; any
+/// other 4xx (notably `404`/`403`) is treated as fatal.
+fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::REQUEST_TIMEOUT
+            | StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Only timeouts and connection failures are worth retrying; anything else
+/// (e.g. a redirect-related error) won't resolve itself on a retry.
+fn is_retryable_error(err: &Error) -> bool {
+    err.is_timeout() || err.is_connect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retries_only_transient_statuses() {
+        for status in [
+            StatusCode::REQUEST_TIMEOUT,
+            StatusCode::TOO_MANY_REQUESTS,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::BAD_GATEWAY,
+            StatusCode::SERVICE_UNAVAILABLE,
+            StatusCode::GATEWAY_TIMEOUT,
+        ] {
+            assert!(is_retryable_status(status), "{} should be retried", status);
+        }
+        for status in [StatusCode::NOT_FOUND, StatusCode::FORBIDDEN, StatusCode::BAD_REQUEST] {
+            assert!(!is_retryable_status(status), "{} should be fatal", status);
+        }
+    }
+
+    #[test]
+    fn gives_up_once_tries_are_exhausted() {
+        let limit = RetryLimit::new(0);
+        assert!(limit.should_retry().is_none());
+    }
+
+    #[test]
+    fn retry_after_parses_integer_seconds() {
+        let mut headers = header::HeaderMap::new();
+        headers.insert(header::RETRY_AFTER, "30".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn retry_after_parses_an_http_date_in_the_future() {
+        let at = SystemTime::now() + Duration::from_secs(60);
+        let mut headers = header::HeaderMap::new();
+        headers.insert(header::RETRY_AFTER, httpdate::fmt_http_date(at).parse().unwrap());
+        let delay = retry_after(&headers).expect("HTTP-date Retry-After should parse");
+        assert!(delay.as_secs() <= 60 && delay.as_secs() >= 55, "{:?}", delay);
+    }
+
+    #[test]
+    fn retry_after_is_none_when_missing_or_unparsable() {
+        assert_eq!(retry_after(&header::HeaderMap::new()), None);
+
+        let mut headers = header::HeaderMap::new();
+        headers.insert(header::RETRY_AFTER, "not a duration".parse().unwrap());
+        assert_eq!(retry_after(&headers), None);
+    }
+
+    #[test]
+    fn backoff_stays_within_the_jittered_window_for_its_attempt() {
+        let limit = RetryLimit::new(10);
+        // `attempt` is `max_tries - remaining_tries`, so the initial call is
+        // attempt 0: base * 2^0, jittered by +/-50%.
+        let delay = limit.backoff();
+        assert!(
+            delay >= limit.base.mul_f64(0.5) && delay <= limit.base.mul_f64(1.5),
+            "{:?}",
+            delay
+        );
+    }
+
+    #[test]
+    fn backoff_never_exceeds_the_jittered_cap() {
+        let mut limit = RetryLimit::new(20);
+        for _ in 0..20 {
+            assert!(limit.backoff() <= limit.cap.mul_f64(1.5));
+            limit = limit.should_retry().unwrap();
         }
     }
 }
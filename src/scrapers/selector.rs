@@ -1,60 +1,198 @@
+use futures::StreamExt;
 use libxml::{parser::Parser, tree::Document};
 use reqwest::Response;
 use url::Url;
 
-use crate::scrapers::EngineError;
+use crate::scrapers::mime::{self, Mime};
+use crate::scrapers::ScrapeError;
 
+/// Default cap on a response body's size, applied by `from_response`. A
+/// pathologically large (or misrouted binary) page is rejected instead of
+/// being buffered into memory whole.
+pub const DEFAULT_MAX_BODY_BYTES: usize = 64 * 1024 * 1024;
+
+/// A parsed response, ready to be queried with XPath.
+///
+/// Binary responses (PDF, images, archives, ...) carry no `Document` and
+/// only `body()`/`mime()` are usable on them; asking `select_links` or
+/// `select_text` of one returns `ScrapeError::UnsupportedMedia`.
 pub struct Selector {
     base_url: Url,
-    doc: Document,
+    mime: Mime,
+    doc: Option<Document>,
+    body: Vec<u8>,
 }
 
 impl Selector {
-    pub fn new(base_url: Url, body: String) -> Result<Self, EngineError> {
+    /// Build a `Selector` for an already-decoded document, parsed with the
+    /// HTML parser; its `mime()` is always `Mime::Html`.
+    pub fn new(base_url: Url, body: String) -> Result<Self, ScrapeError> {
         let doc = Parser::default_html()
             .parse_string(&body)
-            .map_err(|e| EngineError::ParsingError(format!("{:}", e)))?;
+            .map_err(|e| ScrapeError::Parse(format!("{:}", e)))?;
+
+        Ok(Self {
+            base_url,
+            mime: Mime::Html,
+            doc: Some(doc),
+            body: body.into_bytes(),
+        })
+    }
+
+    /// Classify the response by `Content-Type`/magic bytes and dispatch to
+    /// the matching parser, instead of always assuming HTML.
+    ///
+    /// Rejects bodies over `DEFAULT_MAX_BODY_BYTES`; use
+    /// `from_response_with_limit` to configure the cap.
+    pub async fn from_response(response: Response) -> Result<Self, ScrapeError> {
+        Self::from_response_with_limit(response, DEFAULT_MAX_BODY_BYTES).await
+    }
+
+    /// Like `from_response`, but rejects bodies over `max_body_bytes`
+    /// instead of the default 64 MiB, short-circuiting as soon as a
+    /// declared `Content-Length` (or the accumulated body) crosses it.
+    pub async fn from_response_with_limit(
+        response: Response,
+        max_body_bytes: usize,
+    ) -> Result<Self, ScrapeError> {
+        let declared_len = response
+            .headers()
+            .get(reqwest::header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok());
+        if let Some(len) = declared_len {
+            if len > max_body_bytes as u64 {
+                return Err(ScrapeError::BodyTooLarge { limit: max_body_bytes });
+            }
+        }
+
+        let base_url = response.url().to_owned();
+        let headers = response.headers().clone();
+
+        let mut raw = Vec::new();
+        let mut chunks = response.bytes_stream();
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk?;
+            if raw.len() + chunk.len() > max_body_bytes {
+                return Err(ScrapeError::BodyTooLarge { limit: max_body_bytes });
+            }
+            raw.extend_from_slice(&chunk);
+        }
+
+        let mime = Mime::detect(&headers, &raw);
+
+        if !mime.is_markup() {
+            return Ok(Self { base_url, mime, doc: None, body: raw });
+        }
+
+        let charset = mime::declared_charset(&headers).unwrap_or_else(|| "utf-8".to_string());
+        let text = decode_with_charset(&raw, &charset);
+
+        let doc = if mime == Mime::Xml {
+            Parser::default()
+                .parse_string(&text)
+                .map_err(|e| ScrapeError::Parse(format!("{:}", e)))?
+        } else {
+            Parser::default_html()
+                .parse_string(&text)
+                .map_err(|e| ScrapeError::Parse(format!("{:}", e)))?
+        };
+
+        Ok(Self { base_url, mime, doc: Some(doc), body: raw })
+    }
+
+    /// The url this response was fetched from, e.g. for matching against
+    /// an integrity manifest in `scrapers::integrity`.
+    pub fn url(&self) -> &Url {
+        &self.base_url
+    }
+
+    /// The mime type this response was classified as.
+    pub fn mime(&self) -> Mime {
+        self.mime
+    }
 
-        Ok(Self { base_url, doc })
+    /// The raw (pre-decode) response bytes, for binary media, archival, or
+    /// integrity verification.
+    pub fn body(&self) -> &[u8] {
+        &self.body
     }
 
-    pub async fn from_response(response: Response) -> Result<Self, EngineError> {
-        let url = response.url().to_owned();
-        let body = response.text().await?;
-        Selector::new(url, body)
+    fn doc(&self) -> Result<&Document, ScrapeError> {
+        self.doc.as_ref().ok_or(ScrapeError::UnsupportedMedia(self.mime))
     }
 
     /// Extract links using xpath guaranteeing them to be absolute.
-    pub fn select_links(&self, xpath: &str) -> Result<Vec<Url>, EngineError> {
-        let root = self.doc.get_root_element().unwrap();
+    pub fn select_links(&self, xpath: &str) -> Result<Vec<Url>, ScrapeError> {
+        let root = self.doc()?.get_root_element().ok_or(ScrapeError::NoRootElement)?;
         let mut links = vec![];
         let nodes = root
             .findnodes(&xpath)
-            .map_err(|()| EngineError::ParsingError("Invalid XPath".to_string()))?;
+            .map_err(|()| ScrapeError::SelectorNotFound { xpath: xpath.to_string() })?;
         for node in nodes {
             let link = node.get_content();
             let link = self
                 .base_url
                 .join(&link)
-                .map_err(|e| EngineError::ParsingError(format!("Invalid Url: {:}", e)))?;
+                .map_err(|e| ScrapeError::Parse(format!("Invalid Url: {:}", e)))?;
             links.push(link);
         }
         Ok(links)
     }
 
-    pub fn form_data(&self, xpath: &str) -> Result<Vec<(String, String)>, EngineError> {
-        form_values(&self.doc, xpath)
+    /// Extract the text content of every node matched by `xpath`.
+    pub fn select_text(&self, xpath: &str) -> Result<Vec<String>, ScrapeError> {
+        let root = self.doc()?.get_root_element().ok_or(ScrapeError::NoRootElement)?;
+        let nodes = root
+            .findnodes(&xpath)
+            .map_err(|()| ScrapeError::SelectorNotFound { xpath: xpath.to_string() })?;
+        Ok(nodes.into_iter().map(|node| node.get_content()).collect())
     }
+
+    /// Extract an attribute (e.g. `href`) off every node matched by
+    /// `xpath`, resolved to an absolute `Url` via `base_url`.
+    pub fn select_attr(&self, xpath: &str, attr: &str) -> Result<Vec<Url>, ScrapeError> {
+        let root = self.doc()?.get_root_element().ok_or(ScrapeError::NoRootElement)?;
+        let nodes = root
+            .findnodes(xpath)
+            .map_err(|()| ScrapeError::SelectorNotFound { xpath: xpath.to_string() })?;
+        let mut out = vec![];
+        for node in nodes {
+            let value = node
+                .get_attribute(attr)
+                .ok_or_else(|| ScrapeError::Parse(format!("Missing attribute {}", attr)))?;
+            let url = self
+                .base_url
+                .join(&value)
+                .map_err(|e| ScrapeError::Parse(format!("Invalid Url: {:}", e)))?;
+            out.push(url);
+        }
+        Ok(out)
+    }
+
+    pub fn form_data(&self, xpath: &str) -> Result<Vec<(String, String)>, ScrapeError> {
+        form_values(self.doc()?, xpath)
+    }
+}
+
+/// Decode `bytes` using `charset` (e.g. the Bundestag's `ISO-8859-15`),
+/// falling back to a lossy UTF-8 decode for unrecognised charsets.
+fn decode_with_charset(bytes: &[u8], charset: &str) -> String {
+    encoding_rs::Encoding::for_label(charset.as_bytes())
+        .unwrap_or(encoding_rs::UTF_8)
+        .decode(bytes)
+        .0
+        .into_owned()
 }
 
 /// Extract pre-populated form data on a best-effort basis.
-fn form_values(doc: &Document, form_xpath: &str) -> Result<Vec<(String, String)>, EngineError> {
-    let root = doc.get_root_element().expect("No root element found.");
+fn form_values(doc: &Document, form_xpath: &str) -> Result<Vec<(String, String)>, ScrapeError> {
+    let root = doc.get_root_element().ok_or(ScrapeError::NoRootElement)?;
     let form = root
         .findnodes(form_xpath)
-        .map_err(|_: ()| EngineError::ParsingError("Invalid form XPath".to_string()))?
+        .map_err(|_: ()| ScrapeError::SelectorNotFound { xpath: form_xpath.to_string() })?
         .pop()
-        .ok_or_else(|| EngineError::ParsingError("No form-node found".to_string()))?;
+        .ok_or(ScrapeError::FormMissing)?;
     let mut out = vec![];
     // input nodes
     for node in form.findnodes("descendant::input").unwrap() {
@@ -106,6 +244,8 @@ fn form_values(doc: &Document, form_xpath: &str) -> Result<Vec<(String, String)>
 
 #[cfg(test)]
 mod tests {
+    use reqwest::header;
+
     use super::*;
 
     #[test]
@@ -116,4 +256,36 @@ mod tests {
         let values = dbg!(form_values(&doc, "//form").unwrap());
         assert_eq!(values.len(), 99);
     }
+
+    fn response_with(headers: &[(header::HeaderName, &str)], body: Vec<u8>) -> Response {
+        let mut builder = http::Response::builder().status(200);
+        for (name, value) in headers {
+            builder = builder.header(name, *value);
+        }
+        let (mut parts, body) = builder.body(body).unwrap().into_parts();
+        parts.extensions.insert(Url::parse("http://example.com/").unwrap());
+        Response::from(http::Response::from_parts(parts, body))
+    }
+
+    #[tokio::test]
+    async fn rejects_a_declared_content_length_over_the_limit() {
+        let resp = response_with(&[(header::CONTENT_LENGTH, "999")], vec![0u8; 10]);
+        let err = Selector::from_response_with_limit(resp, 100).await.unwrap_err();
+        assert!(matches!(err, ScrapeError::BodyTooLarge { limit: 100 }));
+    }
+
+    #[tokio::test]
+    async fn rejects_a_body_over_the_limit_with_no_declared_content_length() {
+        let resp = response_with(&[(header::CONTENT_TYPE, "text/html")], vec![b'a'; 200]);
+        let err = Selector::from_response_with_limit(resp, 100).await.unwrap_err();
+        assert!(matches!(err, ScrapeError::BodyTooLarge { limit: 100 }));
+    }
+
+    #[tokio::test]
+    async fn accepts_a_body_within_the_limit() {
+        let body = b"<html><body>hi</body></html>".to_vec();
+        let resp = response_with(&[(header::CONTENT_TYPE, "text/html")], body);
+        let sel = Selector::from_response_with_limit(resp, DEFAULT_MAX_BODY_BYTES).await.unwrap();
+        assert_eq!(sel.mime(), Mime::Html);
+    }
 }